@@ -0,0 +1,81 @@
+use ark_bn254::{Bn254, Fr, G1Affine};
+use ark_ec::{AffineRepr, CurveGroup};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use halo2_for_kzgewe::{
+    commit_bits_small, msm_windowed, optimal_window, plain_kzg_com, u64s_to_field_vector,
+    CommitmentKey,
+};
+use halo2_proofs::{
+    halo2curves::{bn256, ff::Field},
+    poly::{commitment::ParamsProver, kzg::commitment::ParamsKZG},
+};
+use rand::rngs::OsRng;
+
+fn commitment_key(n: usize) -> CommitmentKey<Bn254> {
+    CommitmentKey {
+        lagranges: (0..n)
+            .map(|i| (G1Affine::generator() * Fr::from(i as u64 + 1)).into_affine())
+            .collect(),
+    }
+}
+
+fn small_bitvectors(c: &mut Criterion) {
+    let mut group = c.benchmark_group("small bitvectors");
+    for n in [8, 16, 32] {
+        let ck = commitment_key(n);
+        let bits: Vec<bool> = (0..n).map(|i| i % 2 == 0).collect();
+        let evals: Vec<Fr> = bits.iter().map(|b| Fr::from(*b as u64)).collect();
+
+        group.bench_with_input(
+            BenchmarkId::new("commit_bits_small", n),
+            &bits,
+            |b, bits| b.iter(|| commit_bits_small(&ck, bits)),
+        );
+        group.bench_with_input(BenchmarkId::new("plain_kzg_com", n), &evals, |b, evals| {
+            b.iter(|| plain_kzg_com(&ck, evals))
+        });
+    }
+    group.finish();
+}
+
+fn field_conversion(c: &mut Criterion) {
+    let vals: Vec<u64> = (0..1_000_000u64).map(|i| i & 1).collect();
+
+    let mut group = c.benchmark_group("u64 to Fr, 1M elements");
+    group.sample_size(10);
+    group.bench_function("element-wise", |b| {
+        b.iter(|| {
+            vals.iter()
+                .map(|v| halo2_proofs::halo2curves::bn256::Fr::from(*v))
+                .collect::<Vec<_>>()
+        })
+    });
+    group.bench_function("u64s_to_field_vector", |b| {
+        b.iter(|| u64s_to_field_vector(&vals))
+    });
+    group.finish();
+}
+
+fn msm_window(c: &mut Criterion) {
+    let params: ParamsKZG<bn256::Bn256> = ParamsKZG::setup(14, OsRng);
+
+    let mut group = c.benchmark_group("msm window");
+    group.sample_size(10);
+    for log_len in [8, 11, 14] {
+        let len = 1usize << log_len;
+        let bases = &params.get_g()[..len];
+        let scalars: Vec<bn256::Fr> = (0..len).map(|_| bn256::Fr::random(OsRng)).collect();
+
+        group.bench_with_input(BenchmarkId::new("fixed window 4", len), &scalars, |b, s| {
+            b.iter(|| msm_windowed(bases, s, 4))
+        });
+        group.bench_with_input(BenchmarkId::new("optimal_window", len), &scalars, |b, s| {
+            b.iter(|| msm_windowed(bases, s, optimal_window(len)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, small_bitvectors, field_conversion, msm_window);
+criterion_main!(benches);
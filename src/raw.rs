@@ -0,0 +1,24 @@
+//! Commitment math over bare field elements and generator slices.
+//!
+//! Nothing here takes params, keys or circuits: callers that only commit pass
+//! the basis themselves, e.g. a Lagrange basis loaded from disk or built with
+//! [`lagrange_basis_from_monomial`](crate::lagrange_basis_from_monomial).
+//! Functions are generic over the curve, so any halo2curves curve works, not
+//! just BN254.
+
+use halo2_proofs::halo2curves::{group::Curve, CurveAffine};
+
+use crate::{msm_windowed, optimal_window, Error};
+
+/// Commit to `evals` against `basis`, zero-padding positions past the end of
+/// `evals`. With a halo2 Lagrange basis this equals
+/// [`commit_evals`](crate::commit_evals).
+pub fn commit_raw<C: CurveAffine>(basis: &[C], evals: &[C::Scalar]) -> Result<C, Error> {
+    if evals.is_empty() {
+        return Err(Error::EmptyInput);
+    }
+    assert!(evals.len() <= basis.len(), "more values than basis points");
+
+    let basis = &basis[..evals.len()];
+    Ok(msm_windowed(basis, evals, optimal_window(evals.len())).to_affine())
+}
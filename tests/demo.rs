@@ -0,0 +1,27 @@
+use halo2_for_kzgewe::run_demo;
+
+#[test]
+fn demo_commitments_match() {
+    for bits in [
+        vec![0, 0, 1, 1],
+        vec![1, 0, 1],
+        vec![1, 1, 1, 1, 1, 1, 1, 1],
+        vec![0],
+    ] {
+        let output = run_demo(4, &bits).expect("demo should succeed");
+        assert_eq!(output.halo2_commitment, output.plain_commitment);
+    }
+}
+
+#[test]
+fn demo_rejects_non_bits() {
+    assert!(run_demo(4, &[0, 2, 1]).is_err());
+}
+
+#[test]
+fn demo_rejects_empty_input() {
+    assert!(matches!(
+        run_demo(4, &[]),
+        Err(halo2_for_kzgewe::Error::EmptyInput)
+    ));
+}